@@ -1,8 +1,10 @@
 use std::fmt;
 
-use renderer::{canvas_renderer, webgl_renderer, Renderer};
+use fixedbitset::FixedBitSet;
+use renderer::{canvas_renderer, webgl_renderer, Renderer, Theme};
 use wasm_bindgen::prelude::*;
 
+use crate::rule::Rule;
 use crate::timer::Timer;
 use crate::universe_builder::*;
 use crate::Cell::{Alive, Dead};
@@ -11,6 +13,7 @@ use crate::Cell::{Alive, Dead};
 mod utils;
 mod renderer;
 
+pub mod rule;
 pub mod timer;
 pub mod universe_builder;
 
@@ -32,7 +35,10 @@ pub enum Cell {
 pub struct Universe {
     size: Size,
     active: usize,
-    cells: [Vec<Cell>; 2],
+    cells: [FixedBitSet; 2],
+    rule: Rule,
+    theme: Theme,
+    gpu_renderer: Option<webgl_renderer::WebGLRenderer>,
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -55,7 +61,12 @@ impl Size {
 }
 
 impl Universe {
-    fn live_neighbor_count(size: &Size, active_cells: &[Cell], row: usize, column: usize) -> u8 {
+    fn live_neighbor_count(
+        size: &Size,
+        active_cells: &FixedBitSet,
+        row: usize,
+        column: usize,
+    ) -> u8 {
         let north = if row == 0 { size.height - 1 } else { row - 1 };
 
         let south = if row == size.height - 1 { 0 } else { row + 1 };
@@ -86,7 +97,7 @@ impl Universe {
         let mut count = 0;
         for (r, c) in neighbors.iter() {
             let index = size.get_index(*r, *c);
-            count += unsafe { *active_cells.get_unchecked(index) } as u8
+            count += active_cells.contains(index) as u8
         }
 
         count
@@ -95,11 +106,13 @@ impl Universe {
 
 impl fmt::Display for Universe {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
-        for line in self.get_cells().chunks(self.size.width as usize / 8) {
-            for &cell in line {
-                let symbol = match cell {
-                    Alive => '◼',
-                    Dead => '◻',
+        for row in 0..self.size.height {
+            for col in 0..self.size.width {
+                let idx = self.size.get_index(row, col);
+                let symbol = if self.cells[self.active][idx] {
+                    '◼'
+                } else {
+                    '◻'
                 };
                 write!(f, "{}", symbol)?;
             }
@@ -138,35 +151,34 @@ impl Universe {
             };
 
             let _timer = Timer::new("new generation");
+            let rule = &self.rule;
             let mut row = 0usize;
             let mut col = 0usize;
-            for (idx, cell) in active_cells.iter().enumerate() {
+            for idx in 0..active_cells.len() {
                 let live_neighbors = Universe::live_neighbor_count(size, active_cells, row, col);
+                let alive = active_cells[idx];
 
-                let next_cell = match (cell, live_neighbors) {
-                    // Rule 1: Any live cell with fewer than two live neighbours
-                    // dies, as if caused by underpopulation.
-                    (Cell::Alive, x) if x < 2 => Cell::Dead,
-                    // Rule 2: Any live cell with two or three live neighbours
-                    // lives on to the next generation.
-                    (Cell::Alive, 2) | (Cell::Alive, 3) => Cell::Alive,
-                    // Rule 3: Any live cell with more than three live
-                    // neighbours dies, as if by overpopulation.
-                    (Cell::Alive, x) if x > 3 => Cell::Dead,
-                    // Rule 4: Any dead cell with exactly three live neighbours
-                    // becomes a live cell, as if by reproduction.
-                    (Cell::Dead, 3) => Cell::Alive,
-                    // All other cells remain in the same state.
-                    (&otherwise, _) => otherwise,
+                let next_alive = if alive {
+                    rule.survives(live_neighbors)
+                } else {
+                    rule.is_born(live_neighbors)
                 };
                 /*
-                                log!("    it becomes {:?}", next_cell);
+                                log!("    it becomes {:?}", next_alive);
                 */
-                next[idx] = next_cell;
+                next.set(idx, next_alive);
+
+                col += 1;
+                if col == size.width {
+                    col = 0;
+                    row += 1;
+                }
             }
 
             self.active = next_buffer
         }
+
+        self.invalidate_gpu_state();
     }
 
     pub fn width(&self) -> u32 {
@@ -177,8 +189,13 @@ impl Universe {
         self.size.height as u32
     }
 
-    pub fn cells(&self) -> *const Cell {
-        self.get_cells().as_ptr()
+    /// Raw pointer to the active generation's cell bits, packed by
+    /// `fixedbitset`: the buffer is `ceil(width * height / 32)` `u32` words,
+    /// word `idx / 32` bit `idx % 32` holds cell `idx` (row-major, see
+    /// `Size::get_index`), 1 meaning alive. Read it with e.g.
+    /// `new Uint32Array(memory.buffer, ptr, Math.ceil(width * height / 32))`.
+    pub fn cells(&self) -> *const u32 {
+        self.active_cells().as_slice().as_ptr()
     }
 
     pub fn render(&self) -> String {
@@ -186,39 +203,171 @@ impl Universe {
     }
 
     pub fn render_to_canvas_webgl(&self, context: web_sys::WebGlRenderingContext) -> () {
-        let mut renderer = webgl_renderer::WebGLRenderer::init(&context);
+        let mut renderer = webgl_renderer::WebGLRenderer::init(self, &context);
         renderer.render_to_canvas(self, &context);
     }
 
+    /// Advances the automaton `steps` generations entirely on the GPU, via
+    /// ping-ponging WebGL framebuffers. The GPU textures are seeded from the
+    /// CPU-side cells on the first call (and on the first call after any
+    /// CPU-side edit); apart from that upload, no cell data crosses the
+    /// CPU/GPU boundary until `sync_gpu_state` is called.
+    pub fn tick_gpu(&mut self, context: web_sys::WebGlRenderingContext, steps: u32) {
+        if self.gpu_renderer.is_none() {
+            let renderer = webgl_renderer::WebGLRenderer::init(self, &context);
+            self.gpu_renderer = Some(renderer);
+        }
+
+        // Taken out of `self` so `ensure_gpu_state` can still borrow the
+        // universe immutably while seeding from its CPU-side cells.
+        let mut renderer = self.gpu_renderer.take().unwrap();
+        renderer.ensure_gpu_state(self, &context);
+        renderer.tick_gpu(&context, steps, &self.rule);
+        self.gpu_renderer = Some(renderer);
+    }
+
+    /// Reads the GPU simulation's current state back into the CPU-side
+    /// buffer, so `get_cells`/`render`/`toggle_cell` see the result of any
+    /// `tick_gpu` calls made so far.
+    pub fn sync_gpu_state(&mut self, context: web_sys::WebGlRenderingContext) {
+        if let Some(renderer) = &self.gpu_renderer {
+            let active = self.active;
+            self.cells[active] = renderer.read_gpu_state(&context);
+        }
+    }
+
     pub fn render_to_canvas_2d(&self, context: web_sys::CanvasRenderingContext2d) -> () {
-        let mut renderer = canvas_renderer::CanvasRenderer::init(&context);
+        let mut renderer = canvas_renderer::CanvasRenderer::init(self, &context);
         renderer.render_to_canvas(self, &context)
     }
 
     pub fn toggle_cell(&mut self, row: u32, col: u32) {
         let idx = self.size.get_index(row as usize, col as usize);
-        self.get_cells()[idx].toggle();
+        let active = self.active;
+        self.cells[active].toggle(idx);
+        self.invalidate_gpu_state();
+    }
+
+    /// Parses a `B.../S...` rule string (e.g. `"B3/S23"` for Conway,
+    /// `"B36/S23"` for HighLife) and applies it to this universe's ticking.
+    /// Takes effect on the next `tick`/`tick_gpu`. Returns the parse error's
+    /// message as a `JsValue` on an invalid string, leaving the current rule
+    /// unchanged.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        self.rule = rule
+            .parse()
+            .map_err(|err: rule::ParseRuleError| JsValue::from_str(&err.to_string()))?;
+        Ok(())
+    }
+
+    /// Switches both renderers to the light color palette. Takes effect on
+    /// the next render.
+    pub fn set_theme_light(&mut self) {
+        self.set_theme(Theme::LIGHT);
+    }
+
+    /// Switches both renderers to the dark color palette. Takes effect on
+    /// the next render.
+    pub fn set_theme_dark(&mut self) {
+        self.set_theme(Theme::DARK);
+    }
+
+    /// Toggles the cell under a canvas pixel coordinate, for click-to-edit
+    /// UIs. `canvas_width`/`canvas_height` are the canvas's own size (e.g.
+    /// `canvas.clientWidth`/`clientHeight`, already accounting for any
+    /// device-pixel-ratio scaling the caller applied), not the grid's cell
+    /// count. Does nothing if the pixel falls outside the grid.
+    pub fn toggle_cell_at_pixel(&mut self, px: f64, py: f64, canvas_width: f64, canvas_height: f64) {
+        if let Some(idx) = self.cell_index_at_pixel(px, py, canvas_width, canvas_height) {
+            let active = self.active;
+            self.cells[active].toggle(idx);
+            self.invalidate_gpu_state();
+        }
+    }
+
+    /// Like `toggle_cell_at_pixel`, but always forces the hit cell alive
+    /// instead of toggling it, for click-and-drag painting.
+    pub fn paint_cell_at_pixel(&mut self, px: f64, py: f64, canvas_width: f64, canvas_height: f64) {
+        if let Some(idx) = self.cell_index_at_pixel(px, py, canvas_width, canvas_height) {
+            let active = self.active;
+            self.cells[active].set(idx, true);
+            self.invalidate_gpu_state();
+        }
     }
 }
 
 impl Universe {
-    pub fn get_cells(&self) -> &[Cell] {
+    pub fn get_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        let bits = self.active_cells();
+        (0..bits.len()).map(move |idx| if bits[idx] { Alive } else { Dead })
+    }
+
+    pub(crate) fn active_cells(&self) -> &FixedBitSet {
         &self.cells[self.active]
     }
 
     pub fn set_cells(&mut self, cells: &[(usize, usize)]) {
+        let active = self.active;
         for (row, col) in cells.iter().cloned() {
             let idx = self.size.get_index(row, col);
-            self.get_cells()[idx] = Cell::Alive;
+            self.cells[active].set(idx, true);
         }
+        self.invalidate_gpu_state();
+    }
+
+    /// Drops any `tick_gpu`-initialized GPU simulation, so the next
+    /// `tick_gpu` call reseeds its textures from the current CPU-side
+    /// cells. Must be called after every CPU-side edit (`tick`,
+    /// `toggle_cell`, `set_cells`, ...) — GPU and CPU stepping/editing
+    /// can't otherwise be interleaved, since `tick_gpu` only ever reads
+    /// cell state once, at first use.
+    fn invalidate_gpu_state(&mut self) {
+        self.gpu_renderer = None;
+    }
+
+    pub(crate) fn theme(&self) -> Theme {
+        self.theme
+    }
+
+    /// Swaps the color palette used by both renderers. `Theme`'s `[f32; 4]`
+    /// fields aren't wasm-bindgen-marshalable, so this isn't exported
+    /// directly; JS reaches it through `set_theme_light`/`set_theme_dark`
+    /// above.
+    pub(crate) fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
     }
-}
 
-impl Cell {
-    pub fn toggle(&mut self) {
-        *self = match *self {
-            Dead => Alive,
-            Alive => Dead,
+    /// Maps a canvas pixel to the cell index it falls within, or `None` if
+    /// it's outside the grid. Both renderers lay the grid out in a
+    /// 1-unit-per-cell space (see `get_rect`'s cell margin in
+    /// `webgl_renderer`), so this only needs to scale by the grid/canvas
+    /// size ratio; the margin shrinks what's drawn inside each cell, not its
+    /// logical boundary.
+    fn cell_index_at_pixel(
+        &self,
+        px: f64,
+        py: f64,
+        canvas_width: f64,
+        canvas_height: f64,
+    ) -> Option<usize> {
+        if canvas_width <= 0.0
+            || canvas_height <= 0.0
+            || !px.is_finite()
+            || !py.is_finite()
+            || !canvas_width.is_finite()
+            || !canvas_height.is_finite()
+        {
+            return None;
         }
+
+        let col = (px / canvas_width * self.size.width as f64).floor();
+        let row = (py / canvas_height * self.size.height as f64).floor();
+
+        if col < 0.0 || row < 0.0 || col >= self.size.width as f64 || row >= self.size.height as f64
+        {
+            return None;
+        }
+
+        Some(self.size.get_index(row as usize, col as usize))
     }
 }
@@ -1,12 +1,14 @@
-use super::Renderer;
-use crate::{Cell, Universe};
+use super::{Renderer, Theme};
+use crate::Universe;
 use wasm_bindgen::JsValue;
 
-pub struct CanvasRenderer;
+pub struct CanvasRenderer {
+    theme: Theme,
+}
 
 impl CanvasRenderer {
-    fn draw_grid(universe: &Universe, ctx: &web_sys::CanvasRenderingContext2d) -> () {
-        let grid_color: JsValue = JsValue::from_str("#CCCCCC");
+    fn draw_grid(&self, universe: &Universe, ctx: &web_sys::CanvasRenderingContext2d) -> () {
+        let grid_color = rgba_to_css(&self.theme.grid);
         let width = universe.size.width as f64;
         let height = universe.size.height as f64;
         ctx.begin_path();
@@ -28,29 +30,37 @@ impl CanvasRenderer {
         ctx.stroke();
     }
 
-    fn draw_cells(universe: &Universe, ctx: &web_sys::CanvasRenderingContext2d) -> () {
-        let alive_color = JsValue::from_str("#000000");
-        let dead_color = JsValue::from_str("#FFFFFF");
+    fn draw_cells(&self, universe: &Universe, ctx: &web_sys::CanvasRenderingContext2d) -> () {
+        let alive_color = rgba_to_css(&self.theme.alive);
 
         ctx.begin_path();
+        ctx.set_fill_style(&alive_color);
 
-        for (idx, cell) in universe.get_cells().iter().enumerate() {
+        for idx in universe.active_cells().ones() {
             let (y, x) = universe.size.get_address(idx);
-            let color = match cell {
-                Cell::Alive => &alive_color,
-                Cell::Dead => &dead_color,
-            };
-            ctx.set_fill_style(color);
             ctx.fill_rect(x as f64, y as f64, 0.9, 0.9);
         }
     }
 }
 
+fn rgba_to_css(color: &[f32; 4]) -> JsValue {
+    let [r, g, b, a] = *color;
+    JsValue::from_str(&format!(
+        "rgba({}, {}, {}, {})",
+        (r * 255.0) as u8,
+        (g * 255.0) as u8,
+        (b * 255.0) as u8,
+        a
+    ))
+}
+
 impl Renderer for CanvasRenderer {
     type Context = web_sys::CanvasRenderingContext2d;
 
-    fn init(_: &Self::Context) -> Self {
-        CanvasRenderer {}
+    fn init(universe: &Universe, _: &Self::Context) -> Self {
+        CanvasRenderer {
+            theme: universe.theme(),
+        }
     }
 
     fn render_to_canvas(&mut self, universe: &crate::Universe, ctx: &Self::Context) {
@@ -60,7 +70,14 @@ impl Renderer for CanvasRenderer {
             universe.size.width as f64,
             universe.size.height as f64,
         );
-        Self::draw_grid(&universe, ctx);
-        Self::draw_cells(&universe, ctx)
+        ctx.set_fill_style(&rgba_to_css(&self.theme.dead));
+        ctx.fill_rect(
+            0.0,
+            0.0,
+            universe.size.width as f64,
+            universe.size.height as f64,
+        );
+        self.draw_grid(&universe, ctx);
+        self.draw_cells(&universe, ctx)
     }
 }
@@ -1,5 +1,35 @@
 use crate::Universe;
 
+/// An RGBA color palette shared by both renderers, so a universe can switch
+/// appearance (e.g. light/dark mode) without either renderer hardcoding
+/// colors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub alive: [f32; 4],
+    pub dead: [f32; 4],
+    pub grid: [f32; 4],
+}
+
+impl Theme {
+    pub const LIGHT: Theme = Theme {
+        alive: [0.0, 0.0, 0.0, 1.0],
+        dead: [1.0, 1.0, 1.0, 1.0],
+        grid: [0.8, 0.8, 0.8, 1.0],
+    };
+
+    pub const DARK: Theme = Theme {
+        alive: [1.0, 1.0, 1.0, 1.0],
+        dead: [0.0, 0.0, 0.0, 1.0],
+        grid: [0.3, 0.3, 0.3, 1.0],
+    };
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::LIGHT
+    }
+}
+
 pub trait Renderer {
     type Context;
 
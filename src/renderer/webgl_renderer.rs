@@ -1,12 +1,19 @@
-use super::Renderer;
-use crate::{Cell, Size, Universe};
+use fixedbitset::FixedBitSet;
+
+use super::{Renderer, Theme};
+use crate::rule::Rule;
+use crate::{Size, Universe};
 use js_sys::{Float32Array, Int32Array};
-use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext, WebGlShader};
+use web_sys::{
+    WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderingContext, WebGlShader, WebGlTexture,
+};
 
 pub struct WebGLRenderer {
     grid: GridRenderProgram,
     cells: CellRenderProgram,
     size: Size,
+    theme: Theme,
+    gpu: Option<GpuSimulation>,
 }
 
 impl WebGLRenderer {
@@ -18,6 +25,10 @@ impl WebGLRenderer {
             ctx.uniform2i(Some(&size_location), size.width as i32, size.height as i32);
         }
 
+        if let Some(color_location) = ctx.get_uniform_location(&grid.program, "grid_color") {
+            ctx.uniform4fv_with_f32_array(Some(&color_location), &self.theme.grid);
+        }
+
         ctx.bind_buffer(
             WebGlRenderingContext::ARRAY_BUFFER,
             Some(&grid.buffer.buffer),
@@ -36,18 +47,11 @@ impl WebGLRenderer {
         let size = universe.size;
         assert_eq!(size, self.size);
         let cells = &self.cells;
-        let alive_data: Vec<f32> = universe
-            .get_cells()
-            .iter()
-            .flat_map(|cell| {
-                vec![
-                    match cell {
-                        Cell::Alive => 1.0f32,
-                        Cell::Dead => 0.0f32,
-                    };
-                    6
-                ]
-                .into_iter()
+        let bits = universe.active_cells();
+        let alive_data: Vec<f32> = (0..bits.len())
+            .flat_map(|idx| {
+                let value = if bits[idx] { 1.0f32 } else { 0.0f32 };
+                std::iter::repeat(value).take(6)
             })
             .collect();
 
@@ -72,6 +76,14 @@ impl WebGLRenderer {
             ctx.uniform2i(Some(&size_location), size.width as i32, size.height as i32);
         }
 
+        if let Some(alive_location) = ctx.get_uniform_location(&cells.program, "alive_color") {
+            ctx.uniform4fv_with_f32_array(Some(&alive_location), &self.theme.alive);
+        }
+
+        if let Some(dead_location) = ctx.get_uniform_location(&cells.program, "dead_color") {
+            ctx.uniform4fv_with_f32_array(Some(&dead_location), &self.theme.dead);
+        }
+
         ctx.bind_buffer(
             WebGlRenderingContext::ARRAY_BUFFER,
             Some(&cells.rects.buffer),
@@ -111,6 +123,144 @@ impl WebGLRenderer {
             cells.rects.vertex_count as i32,
         );
     }
+
+    /// Builds the GPU ping-pong simulation (shader program, 2 textures, 2
+    /// framebuffers) and seeds it from the universe's current CPU-side
+    /// state, if that hasn't already happened. This is deliberately kept out
+    /// of `Renderer::init`: that constructor also backs the plain,
+    /// non-GPU-ticking `render_to_canvas_webgl` path, which would otherwise
+    /// pay for GPU resources it never uses on every single frame.
+    pub(crate) fn ensure_gpu_state(&mut self, universe: &Universe, ctx: &WebGlRenderingContext) {
+        if self.gpu.is_some() {
+            return;
+        }
+
+        let gpu = initialize_gpu_simulation(self.size, ctx);
+        let bits = universe.active_cells();
+        let rgba = cells_to_rgba(bits, gpu.size);
+
+        ctx.bind_texture(
+            WebGlRenderingContext::TEXTURE_2D,
+            Some(&gpu.textures[gpu.active]),
+        );
+        upload_state_texture(ctx, gpu.size, Some(&rgba));
+
+        self.gpu = Some(gpu);
+    }
+
+    /// Advances the automaton `steps` generations entirely on the GPU,
+    /// ping-ponging between the two off-screen framebuffers. No cell data is
+    /// transferred to or from the CPU. Panics if `ensure_gpu_state` hasn't
+    /// been called yet.
+    pub(crate) fn tick_gpu(&mut self, ctx: &WebGlRenderingContext, steps: u32, rule: &Rule) {
+        let gpu = self
+            .gpu
+            .as_mut()
+            .expect("ensure_gpu_state must run before tick_gpu");
+        let size = gpu.size;
+
+        ctx.viewport(0, 0, size.width as i32, size.height as i32);
+        ctx.use_program(Some(&gpu.program));
+
+        if let Some(location) = ctx.get_uniform_location(&gpu.program, "texel_size") {
+            ctx.uniform2f(
+                Some(&location),
+                1.0 / size.width as f32,
+                1.0 / size.height as f32,
+            );
+        }
+        if let Some(location) = ctx.get_uniform_location(&gpu.program, "birth") {
+            ctx.uniform1fv_with_f32_array(Some(&location), &rule.birth_table());
+        }
+        if let Some(location) = ctx.get_uniform_location(&gpu.program, "survival") {
+            ctx.uniform1fv_with_f32_array(Some(&location), &rule.survival_table());
+        }
+        if let Some(location) = ctx.get_uniform_location(&gpu.program, "state") {
+            ctx.uniform1i(Some(&location), 0);
+        }
+
+        let position_attrib_idx = ctx.get_attrib_location(&gpu.program, "position") as u32;
+        ctx.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&gpu.quad.buffer));
+        ctx.vertex_attrib_pointer_with_i32(
+            position_attrib_idx,
+            2,
+            WebGlRenderingContext::FLOAT,
+            false,
+            0,
+            0,
+        );
+        ctx.enable_vertex_attrib_array(position_attrib_idx);
+
+        for _ in 0..steps {
+            let next = 1 - gpu.active;
+
+            ctx.bind_framebuffer(
+                WebGlRenderingContext::FRAMEBUFFER,
+                Some(&gpu.framebuffers[next]),
+            );
+            ctx.active_texture(WebGlRenderingContext::TEXTURE0);
+            ctx.bind_texture(
+                WebGlRenderingContext::TEXTURE_2D,
+                Some(&gpu.textures[gpu.active]),
+            );
+
+            ctx.draw_arrays(
+                WebGlRenderingContext::TRIANGLE_STRIP,
+                0,
+                gpu.quad.vertex_count as i32,
+            );
+
+            gpu.active = next;
+        }
+
+        ctx.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+    }
+
+    /// Reads the current GPU state back into a `FixedBitSet`. JS only pays
+    /// for this transfer when it explicitly asks for the CPU-visible state.
+    pub(crate) fn read_gpu_state(&self, ctx: &WebGlRenderingContext) -> FixedBitSet {
+        let gpu = self
+            .gpu
+            .as_ref()
+            .expect("ensure_gpu_state must run before read_gpu_state");
+        let size = gpu.size;
+        let mut rgba = vec![0u8; size.width * size.height * 4];
+
+        ctx.bind_framebuffer(
+            WebGlRenderingContext::FRAMEBUFFER,
+            Some(&gpu.framebuffers[gpu.active]),
+        );
+        ctx.read_pixels_with_opt_u8_array(
+            0,
+            0,
+            size.width as i32,
+            size.height as i32,
+            WebGlRenderingContext::RGBA,
+            WebGlRenderingContext::UNSIGNED_BYTE,
+            Some(&mut rgba),
+        )
+        .unwrap();
+        ctx.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+
+        let mut cells = FixedBitSet::with_capacity(size.width * size.height);
+        for idx in 0..cells.len() {
+            cells.set(idx, rgba[idx * 4] > 127);
+        }
+
+        cells
+    }
+}
+
+fn cells_to_rgba(bits: &FixedBitSet, size: Size) -> Vec<u8> {
+    let mut rgba = vec![0u8; size.width * size.height * 4];
+    for idx in 0..bits.len() {
+        let value = if bits[idx] { 255 } else { 0 };
+        rgba[idx * 4] = value;
+        rgba[idx * 4 + 1] = value;
+        rgba[idx * 4 + 2] = value;
+        rgba[idx * 4 + 3] = 255;
+    }
+    rgba
 }
 
 #[repr(C)]
@@ -203,8 +353,10 @@ fn initialize_grid(size: Size, ctx: &WebGlRenderingContext) -> GridRenderProgram
         ctx,
         WebGlRenderingContext::FRAGMENT_SHADER,
         r#"
+    precision mediump float;
+    uniform vec4 grid_color;
     void main() {
-        gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        gl_FragColor = grid_color;
     }
     "#,
     )
@@ -284,11 +436,12 @@ fn initialize_cells(size: Size, ctx: &WebGlRenderingContext) -> CellRenderProgra
         &ctx,
         WebGlRenderingContext::FRAGMENT_SHADER,
         r#"
+        precision mediump float;
         varying lowp float frag_alive;
+        uniform vec4 alive_color;
+        uniform vec4 dead_color;
         void main() {
-            highp vec3 color = vec3(1.0, 1.0, 1.0);
-            color = (1.0 - frag_alive) * color;            
-            gl_FragColor = vec4(color, 1.0);
+            gl_FragColor = mix(dead_color, alive_color, frag_alive);
         }
     "#,
     )
@@ -343,6 +496,168 @@ fn initialize_cells(size: Size, ctx: &WebGlRenderingContext) -> CellRenderProgra
     }
 }
 
+/// GPU-resident simulation state: a full-screen-quad program plus two
+/// single-channel-ish (RGBA, for WebGL1 render-target compatibility) state
+/// textures, each bound to its own framebuffer. Stepping the automaton
+/// renders the quad with `textures[active]` bound as the input sampler and
+/// `framebuffers[1 - active]` bound as the render target, then swaps.
+struct GpuSimulation {
+    program: WebGlProgram,
+    quad: WebGlBufferedData,
+    textures: [WebGlTexture; 2],
+    framebuffers: [WebGlFramebuffer; 2],
+    active: usize,
+    size: Size,
+}
+
+fn initialize_gpu_simulation(size: Size, ctx: &WebGlRenderingContext) -> GpuSimulation {
+    let vertex_shader = compile_shader(
+        ctx,
+        WebGlRenderingContext::VERTEX_SHADER,
+        r#"
+        attribute vec2 position;
+        varying vec2 v_uv;
+        void main() {
+            v_uv = (position + 1.0) * 0.5;
+            gl_Position = vec4(position, 0.0, 1.0);
+        }
+    "#,
+    )
+    .unwrap();
+
+    let frag_shader = compile_shader(
+        ctx,
+        WebGlRenderingContext::FRAGMENT_SHADER,
+        r#"
+        precision mediump float;
+        varying vec2 v_uv;
+        uniform sampler2D state;
+        uniform vec2 texel_size;
+        uniform float birth[9];
+        uniform float survival[9];
+
+        float sample_cell(vec2 offset) {
+            // `fract` wraps the lookup toroidally, matching the CPU
+            // implementation's wraparound neighbor counting.
+            vec2 uv = fract(v_uv + offset * texel_size);
+            return texture2D(state, uv).r > 0.5 ? 1.0 : 0.0;
+        }
+
+        void main() {
+            float alive = sample_cell(vec2(0.0, 0.0));
+
+            int count = int(
+                sample_cell(vec2(-1.0, -1.0)) +
+                sample_cell(vec2(0.0, -1.0)) +
+                sample_cell(vec2(1.0, -1.0)) +
+                sample_cell(vec2(-1.0, 0.0)) +
+                sample_cell(vec2(1.0, 0.0)) +
+                sample_cell(vec2(-1.0, 1.0)) +
+                sample_cell(vec2(0.0, 1.0)) +
+                sample_cell(vec2(1.0, 1.0))
+            );
+
+            float next_alive = alive > 0.5 ? survival[count] : birth[count];
+            gl_FragColor = vec4(next_alive, next_alive, next_alive, 1.0);
+        }
+    "#,
+    )
+    .unwrap();
+
+    let program = link_program(ctx, &vertex_shader, &frag_shader).unwrap();
+
+    let quad_vertices: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+    let quad_buffer = ctx.create_buffer().unwrap();
+    ctx.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&quad_buffer));
+    unsafe {
+        let vertex_array = Float32Array::view(&quad_vertices);
+        ctx.buffer_data_with_array_buffer_view(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &vertex_array,
+            WebGlRenderingContext::STATIC_DRAW,
+        );
+    }
+
+    let textures = [
+        create_state_texture(ctx, size),
+        create_state_texture(ctx, size),
+    ];
+    let framebuffers = [
+        create_framebuffer(ctx, &textures[0]),
+        create_framebuffer(ctx, &textures[1]),
+    ];
+
+    GpuSimulation {
+        program,
+        quad: WebGlBufferedData {
+            buffer: quad_buffer,
+            vertex_count: 4,
+        },
+        textures,
+        framebuffers,
+        active: 0,
+        size,
+    }
+}
+
+fn upload_state_texture(ctx: &WebGlRenderingContext, size: Size, pixels: Option<&[u8]>) {
+    ctx.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        WebGlRenderingContext::TEXTURE_2D,
+        0,
+        WebGlRenderingContext::RGBA as i32,
+        size.width as i32,
+        size.height as i32,
+        0,
+        WebGlRenderingContext::RGBA,
+        WebGlRenderingContext::UNSIGNED_BYTE,
+        pixels,
+    )
+    .unwrap();
+}
+
+fn create_state_texture(ctx: &WebGlRenderingContext, size: Size) -> WebGlTexture {
+    let texture = ctx.create_texture().unwrap();
+    ctx.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&texture));
+    ctx.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_WRAP_S,
+        WebGlRenderingContext::CLAMP_TO_EDGE as i32,
+    );
+    ctx.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_WRAP_T,
+        WebGlRenderingContext::CLAMP_TO_EDGE as i32,
+    );
+    ctx.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_MIN_FILTER,
+        WebGlRenderingContext::NEAREST as i32,
+    );
+    ctx.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_MAG_FILTER,
+        WebGlRenderingContext::NEAREST as i32,
+    );
+    upload_state_texture(ctx, size, None);
+
+    texture
+}
+
+fn create_framebuffer(ctx: &WebGlRenderingContext, texture: &WebGlTexture) -> WebGlFramebuffer {
+    let framebuffer = ctx.create_framebuffer().unwrap();
+    ctx.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&framebuffer));
+    ctx.framebuffer_texture_2d(
+        WebGlRenderingContext::FRAMEBUFFER,
+        WebGlRenderingContext::COLOR_ATTACHMENT0,
+        WebGlRenderingContext::TEXTURE_2D,
+        Some(texture),
+        0,
+    );
+    ctx.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+
+    framebuffer
+}
+
 // Safety: should be safe to do, as Vec2 is declared as #[repr(C)] so we are guaranteed two fields are layed out adjacent
 unsafe fn as_flat_slice<T>(slice: &[Vec2<T>]) -> &[T] {
     let raw_ptr = slice.as_ptr() as *const T;
@@ -366,11 +681,14 @@ impl Renderer for WebGLRenderer {
             grid: initialize_grid(universe.size, context),
             cells: initialize_cells(universe.size, context),
             size: universe.size,
+            theme: universe.theme(),
+            gpu: None,
         }
     }
 
     fn render_to_canvas(&mut self, universe: &Universe, ctx: &Self::Context) {
-        ctx.clear_color(1.0, 1.0, 1.0, 1.0);
+        let [r, g, b, a] = self.theme.dead;
+        ctx.clear_color(r, g, b, a);
         ctx.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
         self.render_grid(universe.size, ctx);
         self.render_cells(universe, ctx);
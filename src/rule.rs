@@ -0,0 +1,113 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A Life-like cellular automaton rule, encoded as two bitmasks.
+///
+/// Bit *n* of `birth` means "a dead cell is born with exactly *n* live
+/// neighbors"; bit *n* of `survival` means "a live cell survives with
+/// exactly *n* live neighbors". Both masks only ever use bits 0..=8, since a
+/// cell has at most eight neighbors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+impl Rule {
+    /// Conway's classic rule, `B3/S23`.
+    pub const CONWAY: Rule = Rule {
+        birth: 1 << 3,
+        survival: (1 << 2) | (1 << 3),
+    };
+
+    pub fn new(birth: u16, survival: u16) -> Self {
+        Rule { birth, survival }
+    }
+
+    pub fn is_born(&self, live_neighbors: u8) -> bool {
+        self.birth & (1 << live_neighbors) != 0
+    }
+
+    pub fn survives(&self, live_neighbors: u8) -> bool {
+        self.survival & (1 << live_neighbors) != 0
+    }
+
+    /// Unpacks `birth` into a neighbor-count-indexed lookup table, for
+    /// GPU shaders that can't do bitwise integer ops (WebGL1's GLSL ES 1.00).
+    pub(crate) fn birth_table(&self) -> [f32; 9] {
+        mask_to_table(self.birth)
+    }
+
+    /// See `birth_table`.
+    pub(crate) fn survival_table(&self) -> [f32; 9] {
+        mask_to_table(self.survival)
+    }
+}
+
+fn mask_to_table(mask: u16) -> [f32; 9] {
+    let mut table = [0.0f32; 9];
+    for (neighbors, slot) in table.iter_mut().enumerate() {
+        *slot = if mask & (1 << neighbors) != 0 { 1.0 } else { 0.0 };
+    }
+    table
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseRuleError(String);
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid rule string: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+/// Parses standard `B.../S...` notation, e.g. `B3/S23` (Conway),
+/// `B36/S23` (HighLife) or `B2/S` (Seeds).
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let birth_part = parts
+            .next()
+            .ok_or_else(|| ParseRuleError(format!("missing birth part in '{}'", s)))?;
+        let survival_part = parts
+            .next()
+            .ok_or_else(|| ParseRuleError(format!("missing survival part in '{}'", s)))?;
+
+        let birth = parse_neighbor_mask(birth_part, 'B')?;
+        let survival = parse_neighbor_mask(survival_part, 'S')?;
+
+        Ok(Rule { birth, survival })
+    }
+}
+
+fn parse_neighbor_mask(part: &str, prefix: char) -> Result<u16, ParseRuleError> {
+    let digits = part
+        .strip_prefix(prefix)
+        .ok_or_else(|| ParseRuleError(format!("expected '{}' prefix in '{}'", prefix, part)))?;
+
+    let mut mask = 0u16;
+    for digit in digits.chars() {
+        let neighbors = digit
+            .to_digit(10)
+            .ok_or_else(|| ParseRuleError(format!("invalid neighbor count '{}' in '{}'", digit, part)))?;
+        if neighbors > 8 {
+            return Err(ParseRuleError(format!(
+                "neighbor count {} out of range 0..=8",
+                neighbors
+            )));
+        }
+        mask |= 1 << neighbors;
+    }
+
+    Ok(mask)
+}
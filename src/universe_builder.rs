@@ -1,4 +1,8 @@
-use crate::{Universe, Cell, utils};
+use fixedbitset::FixedBitSet;
+
+use crate::renderer::Theme;
+use crate::rule::Rule;
+use crate::{Universe, Size, utils};
 
 pub struct UniverseBuilder<T> {
    state: T
@@ -16,9 +20,14 @@ pub struct UniverseWithHeight  {
 
 pub struct ScaledUniverse {
     width: usize,
-    height: usize
+    height: usize,
+    rule: Rule,
+    density: f64,
+    theme: Theme,
 }
 
+const DEFAULT_DENSITY: f64 = 0.5;
+
 impl UniverseBuilder<EmptyUniverse> {
     pub fn with_width(width: usize) -> UniverseBuilder<UniverseWithWidth> {
         utils::set_panic_hook();
@@ -48,7 +57,10 @@ impl UniverseBuilder<UniverseWithWidth> {
         UniverseBuilder {
             state: ScaledUniverse {
                 width: self.state.width,
-                height
+                height,
+                rule: Rule::default(),
+                density: DEFAULT_DENSITY,
+                theme: Theme::default()
             }
         }
     }
@@ -59,59 +71,103 @@ impl UniverseBuilder<UniverseWithHeight> {
         UniverseBuilder {
             state: ScaledUniverse {
                 height: self.state.height,
-                width
+                width,
+                rule: Rule::default(),
+                density: DEFAULT_DENSITY,
+                theme: Theme::default()
             }
         }
     }
 }
 
 impl UniverseBuilder<ScaledUniverse> {
+    /// Overrides the Life-like rule used by the resulting `Universe`.
+    /// Defaults to Conway's own `B3/S23` when not called.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.state.rule = rule;
+        self
+    }
+
+    /// Overrides the alive probability used by `random`/`random_seeded`.
+    /// Defaults to 0.5 when not called.
+    pub fn with_density(mut self, density: f64) -> Self {
+        self.state.density = density;
+        self
+    }
+
+    /// Draws a seed from `Math.random()` and delegates to `random_seeded`, so
+    /// the universe can't be reproduced or shared.
     pub fn random(self) -> Universe {
         use js_sys::Math::random;
 
+        let seed = (random() * u64::MAX as f64) as u64;
+        self.random_seeded(seed)
+    }
+
+    /// Like `random`, but deterministic: the same seed always yields the
+    /// same starting pattern, which makes it possible to share interesting
+    /// configurations and to write reproducible regression tests and
+    /// benchmarks.
+    pub fn random_seeded(self, seed: u64) -> Universe {
+        use rand::Rng;
+        use rand_chacha::rand_core::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
         let width = self.state.width;
         let height = self.state.height;
-        let mut cells = vec![Cell::Dead; width * height];
-        for index in 0..cells.len()
-        {
-            let random = random();
-            cells[index] = if random < 0.5 { Cell::Alive } else { Cell::Dead }
+        let density = self.state.density;
+        let len = width * height;
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut cells = FixedBitSet::with_capacity(len);
+        for index in 0..len {
+            if rng.gen::<f64>() < density {
+                cells.set(index, true);
+            }
         }
 
         Universe {
-            width,
-            height,
-            cells
+            size: Size { width, height },
+            active: 0,
+            cells: [cells.clone(), cells],
+            rule: self.state.rule,
+            theme: self.state.theme,
+            gpu_renderer: None
         }
     }
 
     pub fn empty(self) -> Universe {
         let width = self.state.width;
         let height = self.state.height;
-        let cells = (0..width * height)
-            .map(|_| Cell::Dead)
-            .collect();
+        let cells = FixedBitSet::with_capacity(width * height);
 
         Universe {
-            width,
-            height,
-            cells
+            size: Size { width, height },
+            active: 0,
+            cells: [cells.clone(), cells],
+            rule: self.state.rule,
+            theme: self.state.theme,
+            gpu_renderer: None
         }
     }
 
     pub fn default(self) -> Universe {
         let width = self.state.width;
         let height = self.state.height;
-
-        let mut cells = Vec::with_capacity(width * height);
-        for i in 0..cells.len() {
-            cells[i] = if i % 2 == 0 || i % 7 == 0 { Cell::Alive } else { Cell::Dead };
+        let len = width * height;
+        let mut cells = FixedBitSet::with_capacity(len);
+        for index in 0..len {
+            if index % 2 == 0 || index % 7 == 0 {
+                cells.set(index, true);
+            }
         }
 
         Universe {
-            width,
-            height,
-            cells
+            size: Size { width, height },
+            active: 0,
+            cells: [cells.clone(), cells],
+            rule: self.state.rule,
+            theme: self.state.theme,
+            gpu_renderer: None
         }
     }
 }
\ No newline at end of file